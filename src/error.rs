@@ -0,0 +1,84 @@
+use std::error::Error;
+use std::fmt;
+
+/// Errors which occur when parsing a Tile property.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ParseTileError {
+    /// A map's orientation could not be parsed.
+    OrientationError,
+    /// A tile's wang id could not be parsed.
+    WangIdError,
+}
+
+impl fmt::Display for ParseTileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseTileError::OrientationError => write!(f, "invalid orientation"),
+            ParseTileError::WangIdError => write!(f, "invalid wang id"),
+        }
+    }
+}
+
+impl Error for ParseTileError {}
+
+/// Represents any error that occurred when parsing the requested file.
+#[derive(Debug)]
+pub enum TiledError {
+    /// A attribute was missing, had the wrong type of wasn't formatted
+    /// correctly.
+    MalformedAttributes(String),
+    /// An error occured when decompressing using the flate2 crate.
+    DecompressingError(std::io::Error),
+    /// There was an error decoding base64-encoded tile data.
+    Base64DecodingError(base64::DecodeError),
+    /// An error occurred when decoding a tile's data.
+    InvalidTileEncoding(String),
+    /// Something else entirely happened while parsing the file.
+    Other(String),
+    /// An error occurred when attempting to read a file from the filesystem.
+    CouldNotOpenFile {
+        /// The path that failed to open.
+        path: std::path::PathBuf,
+        /// The reason the file could not be opened.
+        err: std::io::Error,
+    },
+    /// The document ended before it was finished parsing.
+    PrematureEnd(String),
+    /// An error occurred while parsing the XML document itself.
+    XmlDecodingError(xml::reader::Error),
+    /// An error occurred while writing the XML document.
+    XmlEncodingError(xml::writer::Error),
+    /// An error occurred while parsing JSON.
+    JsonDecodingError(String),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiledError::MalformedAttributes(s) => write!(f, "Malformed attributes: {}", s),
+            TiledError::DecompressingError(e) => write!(f, "Error decompressing data: {}", e),
+            TiledError::Base64DecodingError(e) => write!(f, "Error decoding base64 data: {}", e),
+            TiledError::InvalidTileEncoding(s) => write!(f, "Invalid tile data encoding: {}", s),
+            TiledError::Other(s) => write!(f, "{}", s),
+            TiledError::CouldNotOpenFile { path, err } => {
+                write!(f, "Could not open file {:?}: {}", path, err)
+            }
+            TiledError::PrematureEnd(s) => write!(f, "Document ended prematurely: {}", s),
+            TiledError::XmlDecodingError(e) => write!(f, "Error parsing XML: {}", e),
+            TiledError::XmlEncodingError(e) => write!(f, "Error writing XML: {}", e),
+            TiledError::JsonDecodingError(s) => write!(f, "Error parsing JSON: {}", s),
+        }
+    }
+}
+
+impl Error for TiledError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TiledError::DecompressingError(e) => Some(e),
+            TiledError::Base64DecodingError(e) => Some(e),
+            TiledError::XmlDecodingError(e) => Some(e),
+            TiledError::XmlEncodingError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
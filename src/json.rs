@@ -0,0 +1,671 @@
+//! Support for loading Tiled's JSON map format (`.tmj`, historically `.json`)
+//! into the same [`Map`](crate::map::Map) model produced by the TMX/XML
+//! parser, so consumers don't need to care which format an artist exported.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    error::TiledError,
+    layers::{Chunk, ImageLayer, Layer, LayerData},
+    map::Map,
+    objects::{DrawOrder, HorizontalAlignment, Object, ObjectGroup, ObjectShape, VerticalAlignment},
+    properties::{Color, Properties, PropertyValue},
+    tile::{Frame, Gid, LayerTile},
+    tileset::{Image, Tile, Tileset},
+};
+
+#[derive(Debug, Deserialize)]
+struct JsonMap {
+    version: serde_json::Value,
+    orientation: String,
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    backgroundcolor: Option<String>,
+    #[serde(default)]
+    tilesets: Vec<JsonTilesetRef>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonTilesetRef {
+    firstgid: u32,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(flatten)]
+    embedded: Option<JsonTileset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonTileset {
+    name: String,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    margin: u32,
+    tilecount: u32,
+    columns: u32,
+    #[serde(default)]
+    image: Option<String>,
+    #[serde(default)]
+    imagewidth: i32,
+    #[serde(default)]
+    imageheight: i32,
+    #[serde(default)]
+    tiles: Vec<JsonTile>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonTile {
+    id: u32,
+    #[serde(default)]
+    image: Option<String>,
+    #[serde(default)]
+    imagewidth: i32,
+    #[serde(default)]
+    imageheight: i32,
+    #[serde(default)]
+    animation: Option<Vec<JsonFrame>>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFrame {
+    tileid: u32,
+    duration: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonProperty {
+    name: String,
+    #[serde(default)]
+    #[serde(rename = "type")]
+    prop_type: Option<String>,
+    value: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum JsonLayer {
+    Tilelayer(JsonTileLayer),
+    Objectgroup(JsonObjectGroup),
+    Imagelayer(JsonImageLayer),
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonTileLayer {
+    name: String,
+    #[serde(default = "default_opacity")]
+    opacity: f32,
+    #[serde(default = "default_true")]
+    visible: bool,
+    #[serde(default)]
+    data: Option<Vec<u32>>,
+    #[serde(default)]
+    chunks: Option<Vec<JsonChunk>>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonChunk {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    data: Vec<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonObjectGroup {
+    name: String,
+    #[serde(default = "default_opacity")]
+    opacity: f32,
+    #[serde(default = "default_true")]
+    visible: bool,
+    #[serde(default)]
+    color: Option<String>,
+    #[serde(default)]
+    draworder: Option<String>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonImageLayer {
+    name: String,
+    #[serde(default = "default_opacity")]
+    opacity: f32,
+    #[serde(default = "default_true")]
+    visible: bool,
+    #[serde(default)]
+    image: Option<String>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    gid: Option<u32>,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    #[serde(rename = "type")]
+    obj_type: String,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "default_true")]
+    visible: bool,
+    #[serde(default)]
+    ellipse: bool,
+    #[serde(default)]
+    point: bool,
+    #[serde(default)]
+    polygon: Option<Vec<JsonPoint>>,
+    #[serde(default)]
+    polyline: Option<Vec<JsonPoint>>,
+    #[serde(default)]
+    text: Option<JsonText>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonPoint {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonText {
+    text: String,
+    #[serde(default = "default_font_family")]
+    fontfamily: String,
+    #[serde(default = "default_pixel_size")]
+    pixelsize: usize,
+    #[serde(default)]
+    wrap: bool,
+    #[serde(default)]
+    color: Option<String>,
+    #[serde(default)]
+    bold: bool,
+    #[serde(default)]
+    italic: bool,
+    #[serde(default)]
+    underline: bool,
+    #[serde(default)]
+    strikeout: bool,
+    #[serde(default = "default_true")]
+    kerning: bool,
+    #[serde(default)]
+    halign: Option<String>,
+    #[serde(default)]
+    valign: Option<String>,
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+fn default_true() -> bool {
+    true
+}
+fn default_font_family() -> String {
+    "sans-serif".to_string()
+}
+fn default_pixel_size() -> usize {
+    16
+}
+
+impl Map {
+    /// Parses a buffer containing a Tiled JSON (`.tmj`) map into the same
+    /// [`Map`] model [`Map::parse_reader`] produces from TMX. The path may be
+    /// skipped if the map is fully embedded.
+    pub fn parse_json_reader<R: Read>(mut reader: R, path: Option<&Path>) -> Result<Self, TiledError> {
+        let mut contents = String::new();
+        reader
+            .read_to_string(&mut contents)
+            .map_err(|err| TiledError::JsonDecodingError(err.to_string()))?;
+        let json: JsonMap = serde_json::from_str(&contents)
+            .map_err(|err| TiledError::JsonDecodingError(err.to_string()))?;
+        json.into_map(path)
+    }
+
+    /// Parses a file containing a Tiled JSON map. External tilesets (`.tsj`)
+    /// referenced by `source` are loaded relative to `path`.
+    pub fn parse_json_file(path: &Path) -> Result<Self, TiledError> {
+        let file = File::open(path).map_err(|err| TiledError::CouldNotOpenFile {
+            path: path.to_owned(),
+            err,
+        })?;
+        Self::parse_json_reader(file, Some(path))
+    }
+}
+
+impl JsonMap {
+    fn into_map(self, map_path: Option<&Path>) -> Result<Map, TiledError> {
+        let orientation = self
+            .orientation
+            .parse()
+            .map_err(|_| TiledError::MalformedAttributes("invalid orientation".to_string()))?;
+
+        let mut tilesets = Vec::new();
+        for tileset_ref in self.tilesets {
+            tilesets.push(tileset_ref.into_tileset(map_path)?);
+        }
+
+        let mut layers = Vec::new();
+        let mut image_layers = Vec::new();
+        let mut object_groups = Vec::new();
+        let mut layer_index = 0u32;
+        for layer in self.layers {
+            match layer {
+                JsonLayer::Tilelayer(l) => {
+                    layers.push(l.into_layer(self.width, layer_index)?);
+                    layer_index += 1;
+                }
+                JsonLayer::Objectgroup(g) => {
+                    object_groups.push(g.into_object_group(Some(layer_index))?);
+                    layer_index += 1;
+                }
+                JsonLayer::Imagelayer(l) => {
+                    image_layers.push(l.into_image_layer(layer_index));
+                    layer_index += 1;
+                }
+                JsonLayer::Unsupported => {}
+            }
+        }
+
+        Ok(Map {
+            version: json_version_to_string(self.version),
+            orientation,
+            width: self.width,
+            height: self.height,
+            tile_width: self.tilewidth,
+            tile_height: self.tileheight,
+            tilesets,
+            layers,
+            image_layers,
+            object_groups,
+            properties: json_properties(self.properties)?,
+            background_color: self.backgroundcolor.and_then(|c| c.parse().ok()),
+            infinite: self.infinite,
+            source: map_path.map(|p| p.to_owned()),
+        })
+    }
+}
+
+fn json_version_to_string(v: serde_json::Value) -> String {
+    match v {
+        serde_json::Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+impl JsonTilesetRef {
+    fn into_tileset(self, map_path: Option<&Path>) -> Result<Tileset, TiledError> {
+        let first_gid = Gid(self.firstgid);
+        if let Some(source) = self.source {
+            let tsj_path = map_path
+                .and_then(|p| p.parent())
+                .map(|dir| dir.join(&source))
+                .unwrap_or_else(|| PathBuf::from(&source));
+            let contents = std::fs::read_to_string(&tsj_path).map_err(|err| {
+                TiledError::CouldNotOpenFile {
+                    path: tsj_path.clone(),
+                    err,
+                }
+            })?;
+            let tileset: JsonTileset = serde_json::from_str(&contents)
+                .map_err(|err| TiledError::JsonDecodingError(err.to_string()))?;
+            return tileset.into_tileset(first_gid);
+        }
+        self.embedded
+            .ok_or_else(|| {
+                TiledError::MalformedAttributes(
+                    "tileset must either be embedded or have a source".to_string(),
+                )
+            })?
+            .into_tileset(first_gid)
+    }
+}
+
+impl JsonTileset {
+    fn into_tileset(self, first_gid: Gid) -> Result<Tileset, TiledError> {
+        let image = self.image.map(|source| Image {
+            source,
+            width: self.imagewidth,
+            height: self.imageheight,
+        });
+        let mut tiles = HashMap::new();
+        for tile in self.tiles {
+            let id = tile.id;
+            tiles.insert(
+                id,
+                Tile {
+                    id,
+                    image: tile.image.map(|source| Image {
+                        source,
+                        width: tile.imagewidth,
+                        height: tile.imageheight,
+                    }),
+                    animation: tile.animation.map(|frames| {
+                        frames
+                            .into_iter()
+                            .map(|f| Frame {
+                                tile_id: f.tileid,
+                                duration: f.duration,
+                            })
+                            .collect()
+                    }),
+                    properties: json_properties(tile.properties)?,
+                },
+            );
+        }
+        Ok(Tileset {
+            first_gid,
+            name: self.name,
+            tile_width: self.tilewidth,
+            tile_height: self.tileheight,
+            spacing: self.spacing,
+            margin: self.margin,
+            tile_count: self.tilecount,
+            columns: self.columns,
+            image,
+            tiles,
+            // TODO: the JSON format's `wangsets` array uses a different
+            // shape (colors carry a `class` instead of a `name`, `wangid` is
+            // already a plain 8-element array); not parsed yet.
+            wang_sets: Vec::new(),
+            properties: json_properties(self.properties)?,
+        })
+    }
+}
+
+impl JsonTileLayer {
+    fn into_layer(self, width: u32, layer_index: u32) -> Result<Layer, TiledError> {
+        let data = if let Some(chunks) = self.chunks {
+            LayerData::Infinite(chunks.into_iter().map(JsonChunk::into_chunk).collect())
+        } else if let Some(data) = self.data {
+            let rows = data
+                .chunks(width.max(1) as usize)
+                .map(|row| row.iter().map(|&g| LayerTile::from_raw(g)).collect())
+                .collect();
+            LayerData::Finite(rows)
+        } else {
+            return Err(TiledError::MalformedAttributes(format!(
+                "tile layer \"{}\" has neither a data nor a chunks array",
+                self.name
+            )));
+        };
+        Ok(Layer {
+            name: self.name,
+            opacity: self.opacity,
+            visible: self.visible,
+            data,
+            layer_index,
+            properties: json_properties(self.properties)?,
+        })
+    }
+}
+
+impl JsonChunk {
+    fn into_chunk(self) -> Chunk {
+        let tiles = self.data.into_iter().map(LayerTile::from_raw).collect();
+        Chunk {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+            tiles,
+        }
+    }
+}
+
+impl JsonImageLayer {
+    fn into_image_layer(self, layer_index: u32) -> ImageLayer {
+        ImageLayer {
+            name: self.name,
+            image_source: self.image,
+            opacity: self.opacity,
+            visible: self.visible,
+            layer_index,
+            properties: json_properties(self.properties).unwrap_or_default(),
+        }
+    }
+}
+
+impl JsonObjectGroup {
+    fn into_object_group(self, layer_index: Option<u32>) -> Result<ObjectGroup, TiledError> {
+        let mut objects = Vec::new();
+        for object in self.objects {
+            objects.push(object.into_object()?);
+        }
+        let draw_order = match self.draworder.as_deref() {
+            Some("index") => DrawOrder::Index,
+            Some("topdown") | None => DrawOrder::TopDown,
+            Some(other) => {
+                return Err(TiledError::MalformedAttributes(format!(
+                    "unknown draworder: {}",
+                    other
+                )))
+            }
+        };
+        if draw_order == DrawOrder::TopDown {
+            objects.sort_by(|a, b| a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal));
+        }
+        Ok(ObjectGroup {
+            name: self.name,
+            opacity: self.opacity,
+            visible: self.visible,
+            objects,
+            color: self.color.and_then(|c| c.parse().ok()),
+            draw_order,
+            layer_index,
+            properties: json_properties(self.properties)?,
+        })
+    }
+}
+
+impl JsonObject {
+    fn into_object(self) -> Result<Object, TiledError> {
+        let shape = if let Some(text) = self.text {
+            text.into_shape()?
+        } else if self.ellipse {
+            ObjectShape::Ellipse {
+                width: self.width,
+                height: self.height,
+            }
+        } else if self.point {
+            ObjectShape::Point(self.x, self.y)
+        } else if let Some(points) = self.polygon {
+            ObjectShape::Polygon {
+                points: points.into_iter().map(|p| (p.x, p.y)).collect(),
+            }
+        } else if let Some(points) = self.polyline {
+            ObjectShape::Polyline {
+                points: points.into_iter().map(|p| (p.x, p.y)).collect(),
+            }
+        } else {
+            ObjectShape::Rect {
+                width: self.width,
+                height: self.height,
+            }
+        };
+
+        let (gid, flip_h, flip_v, flip_d) = self
+            .gid
+            .map(Gid::from_raw)
+            .unwrap_or((Gid::EMPTY, false, false, false));
+
+        Ok(Object {
+            id: self.id,
+            gid,
+            flip_h,
+            flip_v,
+            flip_d,
+            name: self.name,
+            obj_type: self.obj_type,
+            width: self.width,
+            height: self.height,
+            x: self.x,
+            y: self.y,
+            rotation: self.rotation,
+            visible: self.visible,
+            shape,
+            properties: json_properties(self.properties)?,
+        })
+    }
+}
+
+impl JsonText {
+    fn into_shape(self) -> Result<ObjectShape, TiledError> {
+        let halign = match self.halign.as_deref() {
+            Some("left") | None => HorizontalAlignment::Left,
+            Some("center") => HorizontalAlignment::Center,
+            Some("right") => HorizontalAlignment::Right,
+            Some("justify") => HorizontalAlignment::Justify,
+            Some(other) => {
+                return Err(TiledError::MalformedAttributes(format!(
+                    "unknown halign: {}",
+                    other
+                )))
+            }
+        };
+        let valign = match self.valign.as_deref() {
+            Some("top") | None => VerticalAlignment::Top,
+            Some("center") => VerticalAlignment::Center,
+            Some("bottom") => VerticalAlignment::Bottom,
+            Some(other) => {
+                return Err(TiledError::MalformedAttributes(format!(
+                    "unknown valign: {}",
+                    other
+                )))
+            }
+        };
+        Ok(ObjectShape::Text {
+            font_family: self.fontfamily,
+            pixel_size: self.pixelsize,
+            wrap: self.wrap,
+            color: self
+                .color
+                .and_then(|c| c.parse().ok())
+                .unwrap_or(Color {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                }),
+            bold: self.bold,
+            italic: self.italic,
+            underline: self.underline,
+            strikeout: self.strikeout,
+            kerning: self.kerning,
+            halign,
+            valign,
+            contents: self.text,
+        })
+    }
+}
+
+fn json_properties(props: Vec<JsonProperty>) -> Result<Properties, TiledError> {
+    let mut properties = HashMap::new();
+    for prop in props {
+        let value = match prop.prop_type.as_deref() {
+            Some("int") => PropertyValue::IntValue(prop.value.as_i64().ok_or_else(|| {
+                TiledError::MalformedAttributes(format!("malformed int property: {}", prop.name))
+            })?),
+            Some("float") => PropertyValue::FloatValue(prop.value.as_f64().ok_or_else(|| {
+                TiledError::MalformedAttributes(format!("malformed float property: {}", prop.name))
+            })?),
+            Some("bool") => PropertyValue::BoolValue(prop.value.as_bool().ok_or_else(|| {
+                TiledError::MalformedAttributes(format!("malformed bool property: {}", prop.name))
+            })?),
+            Some("color") => PropertyValue::ColorValue(
+                prop.value
+                    .as_str()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| {
+                        TiledError::MalformedAttributes(format!(
+                            "malformed color property: {}",
+                            prop.name
+                        ))
+                    })?,
+            ),
+            Some("file") => PropertyValue::FileValue(
+                prop.value.as_str().unwrap_or_default().to_string(),
+            ),
+            _ => PropertyValue::StringValue(prop.value.as_str().unwrap_or_default().to_string()),
+        };
+        properties.insert(prop.name, value);
+    }
+    Ok(properties)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::Map;
+
+    #[test]
+    fn json_and_tmx_produce_the_same_map() {
+        let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <layer name="Tile Layer 1">
+  <data encoding="csv">1,2,3,4</data>
+ </layer>
+</map>"#;
+
+        let tmj = r#"{
+            "version": "1.10",
+            "orientation": "orthogonal",
+            "width": 2,
+            "height": 2,
+            "tilewidth": 16,
+            "tileheight": 16,
+            "infinite": false,
+            "layers": [
+                {
+                    "type": "tilelayer",
+                    "name": "Tile Layer 1",
+                    "data": [1, 2, 3, 4]
+                }
+            ]
+        }"#;
+
+        let from_tmx = Map::parse_reader(tmx.as_bytes(), None).unwrap();
+        let from_json = Map::parse_json_reader(tmj.as_bytes(), None).unwrap();
+        assert_eq!(from_tmx, from_json);
+    }
+}
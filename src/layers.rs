@@ -0,0 +1,317 @@
+use std::io::Read;
+
+use xml::{attribute::OwnedAttribute, EventReader};
+
+use crate::{
+    error::TiledError,
+    properties::{parse_properties, Properties},
+    tile::LayerTile,
+    util::{get_attrs, parse_tag},
+};
+
+/// A tile layer, as described by a `<layer>` element.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Layer {
+    /// The layer's name.
+    pub name: String,
+    /// The opacity with which this layer is drawn.
+    pub opacity: f32,
+    /// Whether this layer is visible or not.
+    pub visible: bool,
+    /// The tiles making up this layer.
+    pub data: LayerData,
+    /// This layer's position among its siblings, used to order layers and
+    /// object groups for rendering.
+    pub layer_index: u32,
+    /// The layer's custom properties.
+    pub properties: Properties,
+}
+
+/// The tile data backing a [`Layer`], which is laid out differently
+/// depending on whether the owning map is infinite.
+#[derive(Debug, PartialEq, Clone)]
+pub enum LayerData {
+    /// A regular, fixed-size layer: a `width`-wide grid of tiles, indexed
+    /// `[y][x]`.
+    Finite(Vec<Vec<LayerTile>>),
+    /// An infinite layer's tiles, stored as the list of fixed-size chunks
+    /// Tiled splits them into. Chunk coordinates may be negative, since
+    /// infinite maps have no fixed origin.
+    Infinite(Vec<Chunk>),
+}
+
+/// A single chunk of an infinite layer's `<data>`, as described by a
+/// `<chunk x y width height>` element.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Chunk {
+    /// The X coordinate of this chunk, in tiles. May be negative.
+    pub x: i32,
+    /// The Y coordinate of this chunk, in tiles. May be negative.
+    pub y: i32,
+    /// The width of this chunk, in tiles.
+    pub width: u32,
+    /// The height of this chunk, in tiles.
+    pub height: u32,
+    /// This chunk's tiles, stored row by row: the tile at `(x, y)` relative
+    /// to the chunk's own origin is `tiles[y * width + x]`.
+    pub tiles: Vec<LayerTile>,
+}
+
+/// An image layer, as described by an `<imagelayer>` element.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImageLayer {
+    /// The layer's name.
+    pub name: String,
+    /// The path to the layer's image, if any.
+    pub image_source: Option<String>,
+    /// The opacity with which this layer is drawn.
+    pub opacity: f32,
+    /// Whether this layer is visible or not.
+    pub visible: bool,
+    /// This layer's position among its siblings.
+    pub layer_index: u32,
+    /// The layer's custom properties.
+    pub properties: Properties,
+}
+
+impl Layer {
+    pub(crate) fn new<R: Read>(
+        parser: &mut EventReader<R>,
+        attrs: Vec<OwnedAttribute>,
+        width: u32,
+        layer_index: u32,
+        infinite: bool,
+    ) -> Result<Layer, TiledError> {
+        let ((opacity, visible), (name,)) = get_attrs!(
+            attrs,
+            optionals: [
+                ("opacity", opacity, |v: String| v.parse().ok()),
+                ("visible", visible, |v: String| v.parse().ok().map(|x: i32| x == 1)),
+            ],
+            required: [
+                ("name", name, Some),
+            ],
+            TiledError::MalformedAttributes("layer must have a name".to_string())
+        );
+
+        let mut data = LayerData::Finite(Vec::new());
+        let mut properties = Properties::default();
+        parse_tag!(parser, "layer", {
+            "data" => |attrs| {
+                data = parse_data(parser, attrs, width, infinite)?;
+                Ok(())
+            },
+            "properties" => |_| {
+                properties = parse_properties(parser)?;
+                Ok(())
+            },
+        });
+
+        Ok(Layer {
+            name,
+            opacity: opacity.unwrap_or(1.0),
+            visible: visible.unwrap_or(true),
+            data,
+            layer_index,
+            properties,
+        })
+    }
+}
+
+impl ImageLayer {
+    pub(crate) fn new<R: Read>(
+        parser: &mut EventReader<R>,
+        attrs: Vec<OwnedAttribute>,
+        layer_index: u32,
+    ) -> Result<ImageLayer, TiledError> {
+        let ((opacity, visible), (name,)) = get_attrs!(
+            attrs,
+            optionals: [
+                ("opacity", opacity, |v: String| v.parse().ok()),
+                ("visible", visible, |v: String| v.parse().ok().map(|x: i32| x == 1)),
+            ],
+            required: [
+                ("name", name, Some),
+            ],
+            TiledError::MalformedAttributes("image layer must have a name".to_string())
+        );
+
+        let mut image_source = None;
+        let mut properties = Properties::default();
+        parse_tag!(parser, "imagelayer", {
+            "image" => |attrs: Vec<OwnedAttribute>| {
+                image_source = attrs
+                    .into_iter()
+                    .find(|a| a.name.local_name == "source")
+                    .map(|a| a.value);
+                Ok(())
+            },
+            "properties" => |_| {
+                properties = parse_properties(parser)?;
+                Ok(())
+            },
+        });
+
+        Ok(ImageLayer {
+            name,
+            image_source,
+            opacity: opacity.unwrap_or(1.0),
+            visible: visible.unwrap_or(true),
+            layer_index,
+            properties,
+        })
+    }
+}
+
+/// Parses a `<data>` element's `encoding`/`compression` attributes and body.
+/// For a finite layer this is a flat, `width`-wide grid of tiles; for an
+/// infinite layer (`infinite == true`) Tiled instead nests a `<chunk>` per
+/// fixed-size region of the layer, each carrying its own origin and
+/// independently-encoded payload.
+fn parse_data<R: Read>(
+    parser: &mut EventReader<R>,
+    attrs: Vec<OwnedAttribute>,
+    width: u32,
+    infinite: bool,
+) -> Result<LayerData, TiledError> {
+    let ((encoding, compression), ()) = get_attrs!(
+        attrs,
+        optionals: [
+            ("encoding", encoding, |v: String| Some(v)),
+            ("compression", compression, |v: String| Some(v)),
+        ],
+        required: [],
+        TiledError::MalformedAttributes("data must be well-formed".to_string())
+    );
+
+    if infinite {
+        let mut chunks = Vec::new();
+        parse_tag!(parser, "data", {
+            "chunk" => |attrs| {
+                chunks.push(parse_chunk(parser, attrs, encoding.as_deref(), compression.as_deref())?);
+                Ok(())
+            },
+        });
+        return Ok(LayerData::Infinite(chunks));
+    }
+
+    let mut raw_chars = String::new();
+    loop {
+        match parser.next().map_err(TiledError::XmlDecodingError)? {
+            xml::reader::XmlEvent::Characters(s) => raw_chars.push_str(&s),
+            xml::reader::XmlEvent::EndElement { name, .. } if name.local_name == "data" => break,
+            xml::reader::XmlEvent::EndDocument => {
+                return Err(TiledError::PrematureEnd(
+                    "Document ended before data was parsed".to_string(),
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    let tiles = decode_tiles(raw_chars.trim(), encoding.as_deref(), compression.as_deref())?;
+    Ok(LayerData::Finite(
+        tiles.chunks(width as usize).map(|row| row.to_vec()).collect(),
+    ))
+}
+
+/// Parses a single `<chunk x y width height>` of an infinite layer's data,
+/// decoding its payload exactly like a finite layer's `<data>` body.
+fn parse_chunk<R: Read>(
+    parser: &mut EventReader<R>,
+    attrs: Vec<OwnedAttribute>,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Chunk, TiledError> {
+    let ((), (x, y, width, height)) = get_attrs!(
+        attrs,
+        optionals: [],
+        required: [
+            ("x", x, |v: String| v.parse().ok()),
+            ("y", y, |v: String| v.parse().ok()),
+            ("width", width, |v: String| v.parse().ok()),
+            ("height", height, |v: String| v.parse().ok()),
+        ],
+        TiledError::MalformedAttributes("chunk must have an x, y, width and height".to_string())
+    );
+
+    let mut raw_chars = String::new();
+    loop {
+        match parser.next().map_err(TiledError::XmlDecodingError)? {
+            xml::reader::XmlEvent::Characters(s) => raw_chars.push_str(&s),
+            xml::reader::XmlEvent::EndElement { name, .. } if name.local_name == "chunk" => break,
+            xml::reader::XmlEvent::EndDocument => {
+                return Err(TiledError::PrematureEnd(
+                    "Document ended before chunk was parsed".to_string(),
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    let tiles = decode_tiles(raw_chars.trim(), encoding, compression)?;
+    Ok(Chunk {
+        x,
+        y,
+        width,
+        height,
+        tiles,
+    })
+}
+
+pub(crate) fn decode_tiles(
+    data: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<LayerTile>, TiledError> {
+    match encoding {
+        Some("csv") | None => data
+            .split(',')
+            .map(|s| {
+                s.trim()
+                    .parse()
+                    .map(LayerTile::from_raw)
+                    .map_err(|_| TiledError::InvalidTileEncoding(format!("invalid csv gid: {}", s)))
+            })
+            .collect(),
+        Some("base64") => {
+            let bytes = base64::decode(data).map_err(TiledError::Base64DecodingError)?;
+            let bytes = decompress(bytes, compression)?;
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|b| LayerTile::from_raw(u32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+                .collect())
+        }
+        Some(other) => Err(TiledError::InvalidTileEncoding(format!(
+            "unknown data encoding: {}",
+            other
+        ))),
+    }
+}
+
+fn decompress(bytes: Vec<u8>, compression: Option<&str>) -> Result<Vec<u8>, TiledError> {
+    use std::io::Read as _;
+    match compression {
+        None => Ok(bytes),
+        Some("zlib") => {
+            let mut decoder = flate2::read::ZlibDecoder::new(&bytes[..]);
+            let mut out = Vec::new();
+            decoder
+                .read_to_end(&mut out)
+                .map_err(TiledError::DecompressingError)?;
+            Ok(out)
+        }
+        Some("gzip") => {
+            let mut decoder = flate2::read::GzDecoder::new(&bytes[..]);
+            let mut out = Vec::new();
+            decoder
+                .read_to_end(&mut out)
+                .map_err(TiledError::DecompressingError)?;
+            Ok(out)
+        }
+        Some(other) => Err(TiledError::InvalidTileEncoding(format!(
+            "unknown data compression: {}",
+            other
+        ))),
+    }
+}
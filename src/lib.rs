@@ -0,0 +1,23 @@
+//! A Rust crate for loading [Tiled](https://www.mapeditor.org/) maps, for use
+//! in video games.
+
+pub mod error;
+pub mod json;
+pub mod layers;
+pub mod map;
+pub mod objects;
+pub mod properties;
+pub mod tile;
+pub mod tileset;
+mod util;
+pub mod wangset;
+pub mod writer;
+
+pub use error::{ParseTileError, TiledError};
+pub use layers::{ImageLayer, Layer};
+pub use map::{Map, Orientation};
+pub use objects::{DrawOrder, HorizontalAlignment, Object, ObjectGroup, ObjectShape, VerticalAlignment};
+pub use properties::{Color, Properties, PropertyValue};
+pub use tile::{tile_id_at, Frame, Gid, LayerTile};
+pub use tileset::{Image, Tile, Tileset};
+pub use wangset::{WangColor, WangSet, WangSetType, WangTile};
@@ -65,10 +65,8 @@ impl Map {
             match parser.next().map_err(TiledError::XmlDecodingError)? {
                 XmlEvent::StartElement {
                     name, attributes, ..
-                } => {
-                    if name.local_name == "map" {
-                        return Self::parse_xml(&mut parser, attributes, path);
-                    }
+                } if name.local_name == "map" => {
+                    return Self::parse_xml(&mut parser, attributes, path);
                 }
                 XmlEvent::EndDocument => {
                     return Err(TiledError::PrematureEnd(
@@ -101,7 +99,7 @@ impl Map {
                 ("infinite", infinite, |v:String| Some(v == "1")),
             ],
             required: [
-                ("version", version, |v| Some(v)),
+                ("version", version, Some),
                 ("orientation", orientation, |v:String| v.parse().ok()),
                 ("width", width, |v:String| v.parse().ok()),
                 ("height", height, |v:String| v.parse().ok()),
@@ -156,7 +154,7 @@ impl Map {
             properties,
             background_color: c,
             infinite: infinite.unwrap_or(false),
-            source: map_path.and_then(|p| Some(p.to_owned())),
+            source: map_path.map(|p| p.to_owned()),
         })
     }
 
@@ -1,14 +1,25 @@
-use std::io::Read;
+use std::io::{Read, Write};
 
-use xml::{attribute::OwnedAttribute, EventReader};
+use xml::{attribute::OwnedAttribute, writer::XmlEvent as WriterEvent, EventReader, EventWriter};
 
 use crate::{
     error::TiledError,
-    properties::{Color, Properties},
-    tile::Gid,
+    properties::{parse_properties, write_properties, Color, Properties},
+    tile::{Gid, LayerTile},
     util::{get_attrs, parse_tag},
 };
 
+/// The order in which an [`ObjectGroup`]'s objects should be drawn, as set
+/// by its `draworder` attribute.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DrawOrder {
+    /// Objects are drawn in ascending order of their `y` coordinate, so
+    /// objects lower on the map paint over ones higher up.
+    TopDown,
+    /// Objects are drawn in the order they're listed in the file.
+    Index,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct ObjectGroup {
     /// The object group's name.
@@ -17,10 +28,13 @@ pub struct ObjectGroup {
     pub opacity: f32,
     /// Whether this layer is visible or not.
     pub visible: bool,
-    /// The collection of objects in this object group.
+    /// The collection of objects in this object group, already sorted for
+    /// painting according to `draw_order`.
     pub objects: Vec<Object>,
     /// The color property of this layer.
     pub color: Option<Color>,
+    /// The order in which `objects` should be drawn.
+    pub draw_order: DrawOrder,
     /**
      * Layer index is not preset for tile collision boxes
      */
@@ -35,17 +49,28 @@ impl ObjectGroup {
         attrs: Vec<OwnedAttribute>,
         layer_index: Option<u32>,
     ) -> Result<ObjectGroup, TiledError> {
-        let ((o, v, c, n), ()) = get_attrs!(
+        let ((o, v, c, d, n), ()) = get_attrs!(
             attrs,
             optionals: [
                 ("opacity", opacity, |v:String| v.parse().ok()),
                 ("visible", visible, |v:String| v.parse().ok().map(|x:i32| x == 1)),
                 ("color", color, |v:String| v.parse().ok()),
+                ("draworder", draworder, |v:String| Some(v)),
                 ("name", name, |v:String| v.into()),
             ],
             required: [],
             TiledError::MalformedAttributes("object groups must have a name".to_string())
         );
+        let draw_order = match d.as_deref() {
+            Some("index") => DrawOrder::Index,
+            Some("topdown") | None => DrawOrder::TopDown,
+            Some(other) => {
+                return Err(TiledError::MalformedAttributes(format!(
+                    "unknown draworder: {}",
+                    other
+                )))
+            }
+        };
         let mut objects = Vec::new();
         let mut properties = Properties::default();
         parse_tag!(parser, "objectgroup", {
@@ -54,20 +79,58 @@ impl ObjectGroup {
                 Ok(())
             },
             "properties" => |_| {
-                properties = Properties::parse_xml(parser)?;
+                properties = parse_properties(parser)?;
                 Ok(())
             },
         });
+        if draw_order == DrawOrder::TopDown {
+            objects.sort_by(|a, b| a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal));
+        }
         Ok(ObjectGroup {
             name: n.unwrap_or(String::new()),
             opacity: o.unwrap_or(1.0),
             visible: v.unwrap_or(true),
             objects,
             color: c,
+            draw_order,
             layer_index,
             properties,
         })
     }
+
+    /// Writes this object group out as an `<objectgroup>` element.
+    pub(crate) fn write_xml<W: Write>(
+        &self,
+        writer: &mut EventWriter<W>,
+    ) -> Result<(), TiledError> {
+        let mut start = WriterEvent::start_element("objectgroup").attr("name", &self.name);
+        let opacity_str;
+        if self.opacity != 1.0 {
+            opacity_str = self.opacity.to_string();
+            start = start.attr("opacity", &opacity_str);
+        }
+        if !self.visible {
+            start = start.attr("visible", "0");
+        }
+        let color_str;
+        if let Some(color) = &self.color {
+            color_str = format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue);
+            start = start.attr("color", &color_str);
+        }
+        if self.draw_order == DrawOrder::Index {
+            start = start.attr("draworder", "index");
+        }
+        writer
+            .write(start)
+            .map_err(TiledError::XmlEncodingError)?;
+        for object in &self.objects {
+            object.write_xml(writer)?;
+        }
+        write_properties(writer, &self.properties)?;
+        writer
+            .write(WriterEvent::end_element())
+            .map_err(TiledError::XmlEncodingError)
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -124,8 +187,17 @@ pub enum VerticalAlignment {
 pub struct Object {
     /// The object's ID. Unique for every object from within a map.
     pub id: u32,
-    /// The object's tile GID. If the object is not a tile, this is set to [`Gid::EMPTY`].
+    /// The object's tile GID, with flip/rotation flags already stripped out
+    /// (see [`Gid::from_raw`]). If the object is not a tile, this is set to
+    /// [`Gid::EMPTY`].
     pub gid: Gid,
+    /// Whether the object's tile is flipped horizontally.
+    pub flip_h: bool,
+    /// Whether the object's tile is flipped vertically.
+    pub flip_v: bool,
+    /// Whether the object's tile is flipped along its diagonal. Combined
+    /// with `flip_h`/`flip_v`, this is how Tiled encodes 90° rotations.
+    pub flip_d: bool,
     /// The object's name.
     pub name: String,
     /// The object's type.
@@ -157,7 +229,7 @@ impl Object {
             attrs,
             optionals: [
                 ("id", id, |v:String| v.parse().ok()),
-                ("gid", gid, |v:String| v.parse().ok().and_then(|i| Some(Gid(i)))),
+                ("gid", gid, |v:String| v.parse().ok().map(|raw: u32| Gid::from_raw(raw))),
                 ("name", name, |v:String| v.parse().ok()),
                 ("type", obj_type, |v:String| v.parse().ok()),
                 ("width", width, |v:String| v.parse().ok()),
@@ -176,7 +248,7 @@ impl Object {
         let h = h.unwrap_or(0f32);
         let r = r.unwrap_or(0f32);
         let id = id.unwrap_or(0u32);
-        let gid = gid.unwrap_or(Gid::EMPTY);
+        let (gid, flip_h, flip_v, flip_d) = gid.unwrap_or((Gid::EMPTY, false, false, false));
         let n = n.unwrap_or(String::new());
         let t = t.unwrap_or(String::new());
         let mut shape = None;
@@ -207,7 +279,7 @@ impl Object {
                 Ok(())
             },
             "properties" => |_| {
-                properties = Properties::parse_xml(parser)?;
+                properties = parse_properties(parser)?;
                 Ok(())
             },
         });
@@ -220,6 +292,9 @@ impl Object {
         Ok(Object {
             id,
             gid,
+            flip_h,
+            flip_v,
+            flip_d,
             name: n.clone(),
             obj_type: t.clone(),
             width: w,
@@ -228,35 +303,35 @@ impl Object {
             y,
             rotation: r,
             visible: v,
-            shape: shape,
+            shape,
             properties,
         })
     }
 
     fn new_polyline(attrs: Vec<OwnedAttribute>) -> Result<ObjectShape, TiledError> {
-        let ((), s) = get_attrs!(
+        let ((), (s,)) = get_attrs!(
             attrs,
             optionals: [],
             required: [
-                ("points", points, |v| Some(v)),
+                ("points", points, Some),
             ],
             TiledError::MalformedAttributes("A polyline must have points".to_string())
         );
         let points = Object::parse_points(s)?;
-        Ok(ObjectShape::Polyline { points: points })
+        Ok(ObjectShape::Polyline { points })
     }
 
     fn new_polygon(attrs: Vec<OwnedAttribute>) -> Result<ObjectShape, TiledError> {
-        let ((), s) = get_attrs!(
+        let ((), (s,)) = get_attrs!(
             attrs,
             optionals: [],
             required: [
-                ("points", points, |v| Some(v)),
+                ("points", points, Some),
             ],
             TiledError::MalformedAttributes("A polygon must have points".to_string())
         );
         let points = Object::parse_points(s)?;
-        Ok(ObjectShape::Polygon { points: points })
+        Ok(ObjectShape::Polygon { points })
     }
 
     fn new_point(x: f32, y: f32) -> Result<ObjectShape, TiledError> {
@@ -302,34 +377,51 @@ impl Object {
         );
         let font_family = font_family.unwrap_or_else(|| "sans-serif".to_string());
         let pixel_size = pixel_size.unwrap_or(16);
-        let wrap = if wrap == Some(1) { true } else { false };
+        let wrap = wrap == Some(1);
         let color = color.unwrap_or(Color {
             red: 0,
             green: 0,
             blue: 0,
         });
-        let bold = if bold == Some(1) { true } else { false };
-        let italic = if italic == Some(1) { true } else { false };
-        let underline = if underline == Some(1) { true } else { false };
-        let strikeout = if strikeout == Some(1) { true } else { false };
-        let kerning = if kerning == Some(0) { false } else { true };
+        let bold = bold == Some(1);
+        let italic = italic == Some(1);
+        let underline = underline == Some(1);
+        let strikeout = strikeout == Some(1);
+        let kerning = kerning != Some(0);
         let halign = match halign.as_deref() {
             Some("left") | None => HorizontalAlignment::Left,
             Some("center") => HorizontalAlignment::Center,
             Some("right") => HorizontalAlignment::Right,
             Some("justify") => HorizontalAlignment::Justify,
-            _ => panic!("Unknown halign"),
+            Some(other) => {
+                return Err(TiledError::MalformedAttributes(format!(
+                    "unknown halign: {}",
+                    other
+                )))
+            }
         };
         let valign = match valign.as_deref() {
             Some("top") | None => VerticalAlignment::Top,
             Some("center") => VerticalAlignment::Center,
             Some("bottom") => VerticalAlignment::Bottom,
-            _ => panic!("Unknown halign"),
+            Some(other) => {
+                return Err(TiledError::MalformedAttributes(format!(
+                    "unknown valign: {}",
+                    other
+                )))
+            }
         };
 
         let contents = match parser.next().map_err(TiledError::XmlDecodingError)? {
             xml::reader::XmlEvent::Characters(contents) => contents,
-            _ => panic!(),
+            xml::reader::XmlEvent::EndElement { name, .. } if name.local_name == "text" => {
+                String::new()
+            }
+            _ => {
+                return Err(TiledError::MalformedAttributes(
+                    "text element did not contain character data".to_string(),
+                ))
+            }
         };
 
         Ok(ObjectShape::Text {
@@ -368,4 +460,175 @@ impl Object {
         }
         Ok(points)
     }
+
+    fn points_to_string(points: &[(f32, f32)]) -> String {
+        points
+            .iter()
+            .map(|(x, y)| format!("{},{}", x, y))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Writes this object out as an `<object>` element.
+    pub(crate) fn write_xml<W: Write>(
+        &self,
+        writer: &mut EventWriter<W>,
+    ) -> Result<(), TiledError> {
+        let id_str = self.id.to_string();
+        let mut start = WriterEvent::start_element("object").attr("id", &id_str);
+        if !self.name.is_empty() {
+            start = start.attr("name", &self.name);
+        }
+        if !self.obj_type.is_empty() {
+            start = start.attr("type", &self.obj_type);
+        }
+        let gid_str;
+        if self.gid != Gid::EMPTY {
+            let raw = LayerTile {
+                gid: self.gid,
+                flip_h: self.flip_h,
+                flip_v: self.flip_v,
+                flip_d: self.flip_d,
+            }
+            .to_raw();
+            gid_str = raw.to_string();
+            start = start.attr("gid", &gid_str);
+        }
+        let x_str = self.x.to_string();
+        let y_str = self.y.to_string();
+        start = start.attr("x", &x_str).attr("y", &y_str);
+        let w_str;
+        let h_str;
+        if self.width != 0.0 {
+            w_str = self.width.to_string();
+            start = start.attr("width", &w_str);
+        }
+        if self.height != 0.0 {
+            h_str = self.height.to_string();
+            start = start.attr("height", &h_str);
+        }
+        let rot_str;
+        if self.rotation != 0.0 {
+            rot_str = self.rotation.to_string();
+            start = start.attr("rotation", &rot_str);
+        }
+        if !self.visible {
+            start = start.attr("visible", "0");
+        }
+        writer
+            .write(start)
+            .map_err(TiledError::XmlEncodingError)?;
+
+        match &self.shape {
+            ObjectShape::Rect { .. } => {}
+            ObjectShape::Ellipse { .. } => {
+                writer
+                    .write(WriterEvent::start_element("ellipse"))
+                    .map_err(TiledError::XmlEncodingError)?;
+                writer
+                    .write(WriterEvent::end_element())
+                    .map_err(TiledError::XmlEncodingError)?;
+            }
+            ObjectShape::Point(_, _) => {
+                writer
+                    .write(WriterEvent::start_element("point"))
+                    .map_err(TiledError::XmlEncodingError)?;
+                writer
+                    .write(WriterEvent::end_element())
+                    .map_err(TiledError::XmlEncodingError)?;
+            }
+            ObjectShape::Polygon { points } => {
+                let points_str = Object::points_to_string(points);
+                writer
+                    .write(WriterEvent::start_element("polygon").attr("points", &points_str))
+                    .map_err(TiledError::XmlEncodingError)?;
+                writer
+                    .write(WriterEvent::end_element())
+                    .map_err(TiledError::XmlEncodingError)?;
+            }
+            ObjectShape::Polyline { points } => {
+                let points_str = Object::points_to_string(points);
+                writer
+                    .write(WriterEvent::start_element("polyline").attr("points", &points_str))
+                    .map_err(TiledError::XmlEncodingError)?;
+                writer
+                    .write(WriterEvent::end_element())
+                    .map_err(TiledError::XmlEncodingError)?;
+            }
+            ObjectShape::Text {
+                font_family,
+                pixel_size,
+                wrap,
+                color,
+                bold,
+                italic,
+                underline,
+                strikeout,
+                kerning,
+                halign,
+                valign,
+                contents,
+            } => {
+                let pixel_size_str = pixel_size.to_string();
+                let color_str =
+                    format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue);
+                let mut start = WriterEvent::start_element("text")
+                    .attr("fontfamily", font_family.as_str())
+                    .attr("pixelsize", &pixel_size_str)
+                    .attr("color", &color_str)
+                    .attr("halign", horizontal_alignment_str(*halign))
+                    .attr("valign", vertical_alignment_str(*valign));
+                if *wrap {
+                    start = start.attr("wrap", "1");
+                }
+                if *bold {
+                    start = start.attr("bold", "1");
+                }
+                if *italic {
+                    start = start.attr("italic", "1");
+                }
+                if *underline {
+                    start = start.attr("underline", "1");
+                }
+                if *strikeout {
+                    start = start.attr("strikeout", "1");
+                }
+                if !*kerning {
+                    start = start.attr("kerning", "0");
+                }
+                writer
+                    .write(start)
+                    .map_err(TiledError::XmlEncodingError)?;
+                writer
+                    .write(WriterEvent::characters(contents))
+                    .map_err(TiledError::XmlEncodingError)?;
+                writer
+                    .write(WriterEvent::end_element())
+                    .map_err(TiledError::XmlEncodingError)?;
+            }
+        }
+
+        write_properties(writer, &self.properties)?;
+
+        writer
+            .write(WriterEvent::end_element())
+            .map_err(TiledError::XmlEncodingError)
+    }
+}
+
+fn horizontal_alignment_str(h: HorizontalAlignment) -> &'static str {
+    match h {
+        HorizontalAlignment::Left => "left",
+        HorizontalAlignment::Center => "center",
+        HorizontalAlignment::Right => "right",
+        HorizontalAlignment::Justify => "justify",
+    }
+}
+
+fn vertical_alignment_str(v: VerticalAlignment) -> &'static str {
+    match v {
+        VerticalAlignment::Top => "top",
+        VerticalAlignment::Center => "center",
+        VerticalAlignment::Bottom => "bottom",
+    }
 }
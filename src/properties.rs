@@ -0,0 +1,140 @@
+use std::{collections::HashMap, io::{Read, Write}, str::FromStr};
+
+use xml::{writer::XmlEvent as WriterEvent, EventReader, EventWriter};
+
+use crate::{
+    error::TiledError,
+    util::{get_attrs, parse_tag},
+};
+
+/// An RGB color, as used by maps, object groups and text objects.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Color {
+    /// Red channel.
+    pub red: u8,
+    /// Green channel.
+    pub green: u8,
+    /// Blue channel.
+    pub blue: u8,
+}
+
+impl FromStr for Color {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Color, ()> {
+        let s = s.trim_start_matches('#');
+        // Tiled sometimes writes colors with a leading alpha byte (AARRGGBB);
+        // we only care about the RGB triplet.
+        let s = if s.len() == 8 { &s[2..] } else { s };
+        if s.len() != 6 {
+            return Err(());
+        }
+        let red = u8::from_str_radix(&s[0..2], 16).map_err(|_| ())?;
+        let green = u8::from_str_radix(&s[2..4], 16).map_err(|_| ())?;
+        let blue = u8::from_str_radix(&s[4..6], 16).map_err(|_| ())?;
+        Ok(Color { red, green, blue })
+    }
+}
+
+/// A single custom property value, as Tiled's `<property>` element can hold
+/// several distinct value types.
+#[derive(Debug, PartialEq, Clone)]
+pub enum PropertyValue {
+    /// A `string` property.
+    StringValue(String),
+    /// An `int` property.
+    IntValue(i64),
+    /// A `float` property.
+    FloatValue(f64),
+    /// A `bool` property.
+    BoolValue(bool),
+    /// A `color` property.
+    ColorValue(Color),
+    /// A `file` property, stored as the path it contains.
+    FileValue(String),
+}
+
+/// The set of custom properties attached to a map, layer, tileset, tile or
+/// object.
+pub type Properties = HashMap<String, PropertyValue>;
+
+pub(crate) fn parse_properties<R: Read>(
+    parser: &mut EventReader<R>,
+) -> Result<Properties, TiledError> {
+    let mut properties = HashMap::new();
+    parse_tag!(parser, "properties", {
+        "property" => |attrs: Vec<xml::attribute::OwnedAttribute>| {
+            let ((value, prop_type), (name,)) = get_attrs!(
+                attrs,
+                optionals: [
+                    ("value", value, |v: String| Some(v)),
+                    ("type", prop_type, |v: String| Some(v)),
+                ],
+                required: [
+                    ("name", name, |v: String| Some(v)),
+                ],
+                TiledError::MalformedAttributes("property must have a name".to_string())
+            );
+            let value = value.unwrap_or_default();
+            let value = match prop_type.as_deref() {
+                Some("int") => PropertyValue::IntValue(value.parse().map_err(|_| {
+                    TiledError::MalformedAttributes(format!("malformed int property: {}", value))
+                })?),
+                Some("float") => PropertyValue::FloatValue(value.parse().map_err(|_| {
+                    TiledError::MalformedAttributes(format!("malformed float property: {}", value))
+                })?),
+                Some("bool") => PropertyValue::BoolValue(value == "true"),
+                Some("color") => PropertyValue::ColorValue(value.parse().map_err(|_| {
+                    TiledError::MalformedAttributes(format!("malformed color property: {}", value))
+                })?),
+                Some("file") => PropertyValue::FileValue(value),
+                _ => PropertyValue::StringValue(value),
+            };
+            properties.insert(name, value);
+            Ok(())
+        },
+    });
+    Ok(properties)
+}
+
+/// Writes `properties` out as a `<properties>` element, the counterpart to
+/// [`parse_properties`]. Writes nothing if `properties` is empty.
+pub(crate) fn write_properties<W: Write>(
+    writer: &mut EventWriter<W>,
+    properties: &Properties,
+) -> Result<(), TiledError> {
+    if properties.is_empty() {
+        return Ok(());
+    }
+    writer
+        .write(WriterEvent::start_element("properties"))
+        .map_err(TiledError::XmlEncodingError)?;
+    for (name, value) in properties {
+        let (type_str, value_str) = match value {
+            PropertyValue::StringValue(v) => (None, v.clone()),
+            PropertyValue::IntValue(v) => (Some("int"), v.to_string()),
+            PropertyValue::FloatValue(v) => (Some("float"), v.to_string()),
+            PropertyValue::BoolValue(v) => (Some("bool"), v.to_string()),
+            PropertyValue::ColorValue(v) => (
+                Some("color"),
+                format!("#{:02x}{:02x}{:02x}", v.red, v.green, v.blue),
+            ),
+            PropertyValue::FileValue(v) => (Some("file"), v.clone()),
+        };
+        let mut start = WriterEvent::start_element("property")
+            .attr("name", name.as_str())
+            .attr("value", value_str.as_str());
+        if let Some(type_str) = type_str {
+            start = start.attr("type", type_str);
+        }
+        writer
+            .write(start)
+            .map_err(TiledError::XmlEncodingError)?;
+        writer
+            .write(WriterEvent::end_element())
+            .map_err(TiledError::XmlEncodingError)?;
+    }
+    writer
+        .write(WriterEvent::end_element())
+        .map_err(TiledError::XmlEncodingError)
+}
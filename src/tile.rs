@@ -0,0 +1,128 @@
+/// Set when a tile is flipped horizontally.
+pub const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x8000_0000;
+/// Set when a tile is flipped vertically.
+pub const FLIPPED_VERTICALLY_FLAG: u32 = 0x4000_0000;
+/// Set when a tile is flipped along its top-left/bottom-right diagonal.
+pub const FLIPPED_DIAGONALLY_FLAG: u32 = 0x2000_0000;
+/// Set on maps using the hexagonal orientation to indicate a 120° rotation,
+/// rather than a flip.
+pub const ROTATED_HEXAGONAL_120_FLAG: u32 = 0x1000_0000;
+
+const ALL_FLIP_FLAGS: u32 = FLIPPED_HORIZONTALLY_FLAG
+    | FLIPPED_VERTICALLY_FLAG
+    | FLIPPED_DIAGONALLY_FLAG
+    | ROTATED_HEXAGONAL_120_FLAG;
+
+/// A global tile ID, as used to index into a map's tilesets.
+///
+/// Tiled packs flip/rotation flags into the high bits of the raw ID found in
+/// TMX/JSON files; the flags are already stripped out of any `Gid` this
+/// crate hands back, so `gid.bits()` is always what
+/// [`Map::tileset_by_gid`](crate::map::Map::tileset_by_gid) matches against.
+/// Use [`Gid::from_raw`] (or [`LayerTile::from_raw`]) on the raw value if you
+/// need to recover both the clean GID and the flags.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Gid(pub u32);
+
+impl Gid {
+    /// The GID used to represent an empty tile, i.e. one that is not
+    /// present.
+    pub const EMPTY: Gid = Gid(0);
+
+    /// Returns the raw value of this GID.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Splits a raw GID (as found directly in a `gid` attribute/field) into
+    /// its clean `Gid` and the horizontal/vertical/diagonal flip flags
+    /// packed into its high bits.
+    ///
+    /// Tiled encodes a 90° rotation as a diagonal flip combined with a
+    /// horizontal and/or vertical flip, rather than as a separate rotation
+    /// flag, so callers that want "rotation" need to interpret the three
+    /// booleans together.
+    pub fn from_raw(raw: u32) -> (Gid, bool, bool, bool) {
+        let flip_h = raw & FLIPPED_HORIZONTALLY_FLAG != 0;
+        let flip_v = raw & FLIPPED_VERTICALLY_FLAG != 0;
+        let flip_d = raw & FLIPPED_DIAGONALLY_FLAG != 0;
+        (Gid(raw & !ALL_FLIP_FLAGS), flip_h, flip_v, flip_d)
+    }
+}
+
+/// A single cell of a tile layer: the clean GID to look up in the map's
+/// tilesets, plus the transform flags Tiled packed into the raw GID's high
+/// bits.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LayerTile {
+    /// The tile's GID, with flip/rotation flags already stripped.
+    pub gid: Gid,
+    /// Whether the tile is flipped horizontally.
+    pub flip_h: bool,
+    /// Whether the tile is flipped vertically.
+    pub flip_v: bool,
+    /// Whether the tile is flipped along its diagonal. Combined with
+    /// `flip_h`/`flip_v`, this is how Tiled encodes 90° rotations.
+    pub flip_d: bool,
+}
+
+impl LayerTile {
+    /// Decodes a raw GID (as found in a `<data>` chunk or JSON `data` array)
+    /// into a [`LayerTile`].
+    pub fn from_raw(raw: u32) -> LayerTile {
+        let (gid, flip_h, flip_v, flip_d) = Gid::from_raw(raw);
+        LayerTile {
+            gid,
+            flip_h,
+            flip_v,
+            flip_d,
+        }
+    }
+
+    /// Re-packs this tile's GID and flip flags into the raw value Tiled
+    /// expects in a `<data>` chunk or JSON `data` array.
+    pub fn to_raw(&self) -> u32 {
+        let mut raw = self.gid.0;
+        if self.flip_h {
+            raw |= FLIPPED_HORIZONTALLY_FLAG;
+        }
+        if self.flip_v {
+            raw |= FLIPPED_VERTICALLY_FLAG;
+        }
+        if self.flip_d {
+            raw |= FLIPPED_DIAGONALLY_FLAG;
+        }
+        raw
+    }
+}
+
+/// A single frame of a tile's `<animation>`, mapping a duration to the local
+/// tile ID that should be displayed for it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Frame {
+    /// The local ID, within the tileset, of the tile to display during this
+    /// frame.
+    pub tile_id: u32,
+    /// How long this frame is shown for, in milliseconds.
+    pub duration: u32,
+}
+
+/// Given an elapsed-time accumulator (in milliseconds) and a tile's
+/// animation frames, returns the local tile ID that should currently be
+/// displayed, looping back to the start once the whole animation has played.
+///
+/// Returns `None` if `frames` is empty.
+pub fn tile_id_at(frames: &[Frame], elapsed_ms: u32) -> Option<u32> {
+    let total_duration: u32 = frames.iter().map(|f| f.duration).sum();
+    if total_duration == 0 {
+        return frames.first().map(|f| f.tile_id);
+    }
+    let mut remaining = elapsed_ms % total_duration;
+    for frame in frames {
+        if remaining < frame.duration {
+            return Some(frame.tile_id);
+        }
+        remaining -= frame.duration;
+    }
+    frames.last().map(|f| f.tile_id)
+}
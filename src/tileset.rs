@@ -0,0 +1,286 @@
+use std::{collections::HashMap, fs::File, io::Read, path::Path};
+
+use xml::{attribute::OwnedAttribute, reader::XmlEvent, EventReader};
+
+use crate::{
+    error::TiledError,
+    properties::{parse_properties, Properties},
+    tile::{Frame, Gid},
+    util::{get_attrs, parse_tag},
+    wangset::WangSet,
+};
+
+/// An image embedded in a tileset, either as the single tileset image or as
+/// a per-tile image in an image collection tileset.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Image {
+    /// The path to the image file, relative to the map or tileset that
+    /// references it.
+    pub source: String,
+    /// The image's width in pixels.
+    pub width: i32,
+    /// The image's height in pixels.
+    pub height: i32,
+}
+
+impl Image {
+    fn new(attrs: Vec<OwnedAttribute>) -> Result<Image, TiledError> {
+        let ((), (source, width, height)) = get_attrs!(
+            attrs,
+            optionals: [],
+            required: [
+                ("source", source, Some),
+                ("width", width, |v: String| v.parse().ok()),
+                ("height", height, |v: String| v.parse().ok()),
+            ],
+            TiledError::MalformedAttributes("image must have a source, width and height".to_string())
+        );
+        Ok(Image {
+            source,
+            width,
+            height,
+        })
+    }
+}
+
+/// A single tile's extra data, when it differs from the tileset's defaults
+/// (an image, custom properties, an animation).
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Tile {
+    /// The tile's local ID within its tileset.
+    pub id: u32,
+    /// The tile's image, for image collection tilesets.
+    pub image: Option<Image>,
+    /// The tile's animation frames, if it has one, in playback order.
+    pub animation: Option<Vec<Frame>>,
+    /// The tile's custom properties.
+    pub properties: Properties,
+}
+
+/// A set of tiles, as described by a `<tileset>` element, either embedded in
+/// a map or loaded from an external TSX file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tileset {
+    /// The GID of the first tile in this tileset.
+    pub first_gid: Gid,
+    /// The tileset's name.
+    pub name: String,
+    /// The width of the tiles in this tileset, in pixels.
+    pub tile_width: u32,
+    /// The height of the tiles in this tileset, in pixels.
+    pub tile_height: u32,
+    /// The spacing between tiles in this tileset, in pixels.
+    pub spacing: u32,
+    /// The margin around the tiles in this tileset, in pixels.
+    pub margin: u32,
+    /// The number of tiles in this tileset.
+    pub tile_count: u32,
+    /// The number of columns in this tileset's image.
+    pub columns: u32,
+    /// The tileset's single image, for non-collection tilesets.
+    pub image: Option<Image>,
+    /// Per-tile data, keyed by local tile ID, for tiles that have a `<tile>`
+    /// element. Only a tile's image, animation and custom properties are
+    /// kept; its type and collision shapes are not stored.
+    pub tiles: HashMap<u32, Tile>,
+    /// The Wang sets (terrain/corner/edge coloring for auto-tiling) defined
+    /// on this tileset.
+    pub wang_sets: Vec<WangSet>,
+    /// The tileset's custom properties.
+    pub properties: Properties,
+}
+
+impl Tileset {
+    pub(crate) fn parse_xml<R: Read>(
+        parser: &mut EventReader<R>,
+        attrs: Vec<OwnedAttribute>,
+        map_path: Option<&Path>,
+    ) -> Result<Tileset, TiledError> {
+        let ((spacing, margin, source), (first_gid,)) = get_attrs!(
+            attrs.clone(),
+            optionals: [
+                ("spacing", spacing, |v: String| v.parse::<u32>().ok()),
+                ("margin", margin, |v: String| v.parse::<u32>().ok()),
+                ("source", source, Some),
+            ],
+            required: [
+                ("firstgid", first_gid, |v: String| v.parse().ok().map(Gid)),
+            ],
+            TiledError::MalformedAttributes("tileset must have a firstgid".to_string())
+        );
+
+        // An external tileset just points at a TSX file with a path relative
+        // to the map that references it; load and parse that instead.
+        if let Some(source) = source {
+            let tsx_path = map_path
+                .and_then(|p| p.parent())
+                .map(|dir| dir.join(&source))
+                .unwrap_or_else(|| Path::new(&source).to_owned());
+            let file = File::open(&tsx_path).map_err(|err| TiledError::CouldNotOpenFile {
+                path: tsx_path.clone(),
+                err,
+            })?;
+            let mut tsx_parser = EventReader::new(file);
+            loop {
+                match tsx_parser.next().map_err(TiledError::XmlDecodingError)? {
+                    XmlEvent::StartElement {
+                        name, attributes, ..
+                    } if name.local_name == "tileset" => {
+                        return Tileset::parse_xml_embedded(
+                            &mut tsx_parser,
+                            attributes,
+                            first_gid,
+                            Some(&tsx_path),
+                        );
+                    }
+                    XmlEvent::EndDocument => {
+                        return Err(TiledError::PrematureEnd(
+                            "Document ended before tileset was parsed".to_string(),
+                        ))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let tileset = Tileset::parse_xml_embedded(parser, attrs, first_gid, map_path)?;
+        let _ = (spacing, margin);
+        Ok(tileset)
+    }
+
+    fn parse_xml_embedded<R: Read>(
+        parser: &mut EventReader<R>,
+        attrs: Vec<OwnedAttribute>,
+        first_gid: Gid,
+        _tileset_path: Option<&Path>,
+    ) -> Result<Tileset, TiledError> {
+        let ((spacing, margin), (name, tile_width, tile_height, tile_count, columns)) = get_attrs!(
+            attrs,
+            optionals: [
+                ("spacing", spacing, |v: String| v.parse().ok()),
+                ("margin", margin, |v: String| v.parse().ok()),
+            ],
+            required: [
+                ("name", name, Some),
+                ("tilewidth", tile_width, |v: String| v.parse().ok()),
+                ("tileheight", tile_height, |v: String| v.parse().ok()),
+                ("tilecount", tile_count, |v: String| v.parse().ok()),
+                ("columns", columns, |v: String| v.parse().ok()),
+            ],
+            TiledError::MalformedAttributes(
+                "tileset must have a name, tilewidth, tileheight, tilecount and columns".to_string()
+            )
+        );
+
+        let mut image = None;
+        let mut tiles = HashMap::new();
+        let mut wang_sets = Vec::new();
+        let mut properties = Properties::default();
+        parse_tag!(parser, "tileset", {
+            "image" => |attrs| {
+                image = Some(Image::new(attrs)?);
+                Ok(())
+            },
+            "tile" => |attrs| {
+                let (id, tile) = Tile::parse_xml(parser, attrs)?;
+                tiles.insert(id, tile);
+                Ok(())
+            },
+            "wangsets" => |_| {
+                parse_tag!(parser, "wangsets", {
+                    "wangset" => |attrs| {
+                        wang_sets.push(WangSet::parse_xml(parser, attrs)?);
+                        Ok(())
+                    },
+                });
+                Ok(())
+            },
+            "properties" => |_| {
+                properties = parse_properties(parser)?;
+                Ok(())
+            },
+        });
+
+        Ok(Tileset {
+            first_gid,
+            name,
+            tile_width,
+            tile_height,
+            spacing: spacing.unwrap_or(0),
+            margin: margin.unwrap_or(0),
+            tile_count,
+            columns,
+            image,
+            tiles,
+            wang_sets,
+            properties,
+        })
+    }
+
+    /// Returns whether the given GID belongs to a tile from this tileset.
+    pub fn contains_tile(&self, gid: Gid) -> bool {
+        gid.0 >= self.first_gid.0 && gid.0 < self.first_gid.0 + self.tile_count
+    }
+}
+
+impl Tile {
+    fn parse_xml<R: Read>(
+        parser: &mut EventReader<R>,
+        attrs: Vec<OwnedAttribute>,
+    ) -> Result<(u32, Tile), TiledError> {
+        let ((), (id,)) = get_attrs!(
+            attrs,
+            optionals: [],
+            required: [
+                ("id", id, |v: String| v.parse().ok()),
+            ],
+            TiledError::MalformedAttributes("tile must have an id".to_string())
+        );
+        let mut image = None;
+        let mut animation = None;
+        let mut properties = Properties::default();
+        parse_tag!(parser, "tile", {
+            "image" => |attrs| {
+                image = Some(Image::new(attrs)?);
+                Ok(())
+            },
+            "animation" => |_| {
+                let mut frames = Vec::new();
+                parse_tag!(parser, "animation", {
+                    "frame" => |attrs| {
+                        frames.push(parse_frame(attrs)?);
+                        Ok(())
+                    },
+                });
+                animation = Some(frames);
+                Ok(())
+            },
+            "properties" => |_| {
+                properties = parse_properties(parser)?;
+                Ok(())
+            },
+        });
+        Ok((
+            id,
+            Tile {
+                id,
+                image,
+                animation,
+                properties,
+            },
+        ))
+    }
+}
+
+fn parse_frame(attrs: Vec<OwnedAttribute>) -> Result<Frame, TiledError> {
+    let ((), (tile_id, duration)) = get_attrs!(
+        attrs,
+        optionals: [],
+        required: [
+            ("tileid", tile_id, |v: String| v.parse().ok()),
+            ("duration", duration, |v: String| v.parse().ok()),
+        ],
+        TiledError::MalformedAttributes("frame must have a tileid and duration".to_string())
+    );
+    Ok(Frame { tile_id, duration })
+}
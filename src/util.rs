@@ -0,0 +1,89 @@
+use std::io::Read;
+
+use xml::reader::XmlEvent;
+use xml::EventReader;
+
+use crate::error::TiledError;
+
+/// Scans `$attrs` once, pulling out the optional and required attributes by
+/// name and running each one through its conversion closure. Missing or
+/// unparsable required attributes bail out with `$err`.
+macro_rules! get_attrs {
+    ($attrs:expr, optionals: [$(($oName:pat, $oVar:ident, $oMethod:expr)),* $(,)*],
+                   required: [$(($name:pat, $var:ident, $method:expr)),* $(,)*], $err:expr) => {
+        {
+            $(let mut $oVar = None;)*
+            $(let mut $var = None;)*
+            for attr in $attrs {
+                match attr.name.local_name.as_str() {
+                    $($oName => $oVar = $oMethod(attr.value),)*
+                    $($name => $var = $method(attr.value),)*
+                    _ => {}
+                }
+            }
+            if !(true $(&& $var.is_some())*) {
+                return Err($err);
+            }
+            (($($oVar,)*), ($($var.unwrap(),)*))
+        }
+    }
+}
+
+/// Reads through `$parser` until the `$close_tag` end element is found,
+/// dispatching any recognized `$open_tag` start elements to their handler and
+/// silently skipping over any other subtree.
+macro_rules! parse_tag {
+    ($parser:expr, $close_tag:expr, {$($open_tag:expr => $open_method:expr),* $(,)*}) => {
+        loop {
+            match $parser.next().map_err(TiledError::XmlDecodingError)? {
+                ::xml::reader::XmlEvent::StartElement { name, attributes, .. } => {
+                    if false {
+                        unreachable!();
+                    }
+                    $(else if name.local_name == $open_tag {
+                        $open_method(attributes)?;
+                    })*
+                    else {
+                        crate::util::skip_tag($parser)?;
+                    }
+                }
+                ::xml::reader::XmlEvent::EndElement { name, .. } if name.local_name == $close_tag => {
+                    break;
+                }
+                ::xml::reader::XmlEvent::EndDocument => {
+                    return Err(TiledError::PrematureEnd(format!(
+                        "Document ended before {} was parsed", $close_tag
+                    )));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+pub(crate) use get_attrs;
+pub(crate) use parse_tag;
+
+/// Consumes the current subtree (everything up to and including its matching
+/// end element) without interpreting it. Used by [`parse_tag`] to ignore
+/// elements this crate doesn't understand yet.
+pub(crate) fn skip_tag<R: Read>(parser: &mut EventReader<R>) -> Result<(), TiledError> {
+    let mut depth = 1u32;
+    loop {
+        match parser.next().map_err(TiledError::XmlDecodingError)? {
+            XmlEvent::StartElement { .. } => depth += 1,
+            XmlEvent::EndElement { .. } => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            }
+            XmlEvent::EndDocument => {
+                return Err(TiledError::PrematureEnd(
+                    "Document ended before an element was closed".to_string(),
+                ))
+            }
+            _ => {}
+        }
+    }
+}
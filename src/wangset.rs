@@ -0,0 +1,181 @@
+use std::io::Read;
+
+use xml::{attribute::OwnedAttribute, EventReader};
+
+use crate::{
+    error::TiledError,
+    properties::Color,
+    util::{get_attrs, parse_tag},
+};
+
+/// A Wang set, describing the terrain/corner/edge colors Tiled's terrain
+/// brush uses to drive auto-tiling, as parsed from a tileset's `<wangsets>`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WangSet {
+    /// The Wang set's name.
+    pub name: String,
+    /// Whether `wang_id` entries assign colors to corners, edges or both.
+    pub wang_set_type: WangSetType,
+    /// The local ID of the tile representing this Wang set in the editor.
+    pub tile: i32,
+    /// The colors this Wang set assigns to corners/edges.
+    pub colors: Vec<WangColor>,
+    /// Per-tile corner/edge color assignments.
+    pub wang_tiles: Vec<WangTile>,
+}
+
+/// What parts of a tile's `wang_id` a [`WangSet`] assigns colors to, as set
+/// by its `type` attribute.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WangSetType {
+    /// Only the corner indices of `wang_id` are used.
+    Corner,
+    /// Only the edge indices of `wang_id` are used.
+    Edge,
+    /// Both the corner and edge indices of `wang_id` are used.
+    Mixed,
+}
+
+/// A single named color a [`WangSet`] can assign to a tile's corners/edges.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WangColor {
+    /// The color's name.
+    pub name: String,
+    /// The color itself.
+    pub color: Color,
+    /// The local ID of the tile representing this color in the editor.
+    pub tile: i32,
+    /// The relative probability this color is chosen over others while
+    /// auto-tiling.
+    pub probability: f32,
+}
+
+/// A tile's corner/edge color assignment within a [`WangSet`].
+///
+/// `wang_id` holds 8 color indices, one per corner and edge, going
+/// clockwise from the top edge: `[top, top-right, right, bottom-right,
+/// bottom, bottom-left, left, top-left]`. An index of `0` means "no color
+/// assigned".
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct WangTile {
+    /// The local ID of the tile this entry describes.
+    pub tile_id: u32,
+    /// The 8 corner/edge color indices assigned to this tile.
+    pub wang_id: [u8; 8],
+}
+
+impl WangSet {
+    pub(crate) fn parse_xml<R: Read>(
+        parser: &mut EventReader<R>,
+        attrs: Vec<OwnedAttribute>,
+    ) -> Result<WangSet, TiledError> {
+        let ((tile, wang_set_type), (name,)) = get_attrs!(
+            attrs,
+            optionals: [
+                ("tile", tile, |v: String| v.parse().ok()),
+                ("type", wang_set_type, |v: String| Some(v)),
+            ],
+            required: [
+                ("name", name, Some),
+            ],
+            TiledError::MalformedAttributes("wangset must have a name".to_string())
+        );
+        let wang_set_type = match wang_set_type.as_deref() {
+            Some("corner") => WangSetType::Corner,
+            Some("edge") => WangSetType::Edge,
+            Some("mixed") | None => WangSetType::Mixed,
+            Some(other) => {
+                return Err(TiledError::MalformedAttributes(format!(
+                    "unknown wangset type: {}",
+                    other
+                )))
+            }
+        };
+
+        let mut colors = Vec::new();
+        let mut wang_tiles = Vec::new();
+        parse_tag!(parser, "wangset", {
+            "wangcolor" => |attrs| {
+                colors.push(WangColor::parse_xml(attrs)?);
+                Ok(())
+            },
+            "wangtile" => |attrs| {
+                wang_tiles.push(WangTile::parse_xml(attrs)?);
+                Ok(())
+            },
+        });
+
+        Ok(WangSet {
+            name,
+            wang_set_type,
+            tile: tile.unwrap_or(-1),
+            colors,
+            wang_tiles,
+        })
+    }
+}
+
+impl WangColor {
+    fn parse_xml(attrs: Vec<OwnedAttribute>) -> Result<WangColor, TiledError> {
+        let ((tile, probability), (name, color)) = get_attrs!(
+            attrs,
+            optionals: [
+                ("tile", tile, |v: String| v.parse().ok()),
+                ("probability", probability, |v: String| v.parse().ok()),
+            ],
+            required: [
+                ("name", name, Some),
+                ("color", color, |v: String| v.parse().ok()),
+            ],
+            TiledError::MalformedAttributes("wangcolor must have a name and color".to_string())
+        );
+        Ok(WangColor {
+            name,
+            color,
+            tile: tile.unwrap_or(-1),
+            probability: probability.unwrap_or(1.0),
+        })
+    }
+}
+
+impl WangTile {
+    fn parse_xml(attrs: Vec<OwnedAttribute>) -> Result<WangTile, TiledError> {
+        let ((), (tile_id, wang_id)) = get_attrs!(
+            attrs,
+            optionals: [],
+            required: [
+                ("tileid", tile_id, |v: String| v.parse().ok()),
+                ("wangid", wang_id, |v: String| Some(v)),
+            ],
+            TiledError::MalformedAttributes("wangtile must have a tileid and wangid".to_string())
+        );
+        Ok(WangTile {
+            tile_id,
+            wang_id: parse_wang_id(&wang_id)?,
+        })
+    }
+}
+
+/// Parses a `wangid`, either as the 8 comma-separated corner/edge color
+/// indices modern Tiled writes, or as the single packed hex value older
+/// versions used.
+fn parse_wang_id(s: &str) -> Result<[u8; 8], TiledError> {
+    if s.contains(',') {
+        let values: Vec<u8> = s
+            .split(',')
+            .map(|v| v.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| TiledError::MalformedAttributes(format!("invalid wangid: {}", s)))?;
+        values
+            .try_into()
+            .map_err(|_| TiledError::MalformedAttributes(format!("wangid must have 8 values: {}", s)))
+    } else {
+        let packed = u32::from_str_radix(s.trim_start_matches("0x"), 16)
+            .map_err(|_| TiledError::MalformedAttributes(format!("invalid wangid: {}", s)))?;
+        let mut wang_id = [0u8; 8];
+        for (i, slot) in wang_id.iter_mut().enumerate() {
+            *slot = ((packed >> (i * 4)) & 0xF) as u8;
+        }
+        Ok(wang_id)
+    }
+}
@@ -0,0 +1,300 @@
+//! Serializes a [`Map`] back out to Tiled's TMX (XML) format, the
+//! counterpart to the parsing done in [`crate::map`]. This is what makes the
+//! crate usable as an editing/transform tool and not just a reader.
+//!
+//! Custom properties round-trip on the map, tilesets, layers, object groups
+//! and objects. Per-tile data (properties, animations, images) and Wang sets
+//! do not: they're read by [`crate::tileset`] but never written back out.
+
+use std::{fs::File, io::Write, path::Path};
+
+use xml::{writer::XmlEvent as WriterEvent, EmitterConfig, EventWriter};
+
+use crate::{
+    error::TiledError,
+    layers::{ImageLayer, Layer, LayerData},
+    map::Map,
+    properties::write_properties,
+    tileset::Tileset,
+};
+
+impl Map {
+    /// Serializes this map to a writer as TMX.
+    pub fn write_xml<W: Write>(&self, writer: W) -> Result<(), TiledError> {
+        let mut writer = EmitterConfig::new()
+            .perform_indent(true)
+            .create_writer(writer);
+
+        let infinite_str = if self.infinite { "1" } else { "0" };
+        let orientation_str = self.orientation.to_string();
+        let w_str = self.width.to_string();
+        let h_str = self.height.to_string();
+        let tw_str = self.tile_width.to_string();
+        let th_str = self.tile_height.to_string();
+        let mut start = WriterEvent::start_element("map")
+            .attr("version", &self.version)
+            .attr("orientation", &orientation_str)
+            .attr("width", &w_str)
+            .attr("height", &h_str)
+            .attr("tilewidth", &tw_str)
+            .attr("tileheight", &th_str)
+            .attr("infinite", infinite_str);
+        let bg_str;
+        if let Some(color) = &self.background_color {
+            bg_str = format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue);
+            start = start.attr("backgroundcolor", &bg_str);
+        }
+        writer
+            .write(start)
+            .map_err(TiledError::XmlEncodingError)?;
+
+        for tileset in &self.tilesets {
+            write_tileset(&mut writer, tileset)?;
+        }
+        for layer in &self.layers {
+            write_layer(&mut writer, layer)?;
+        }
+        for image_layer in &self.image_layers {
+            write_image_layer(&mut writer, image_layer)?;
+        }
+        for object_group in &self.object_groups {
+            object_group.write_xml(&mut writer)?;
+        }
+        write_properties(&mut writer, &self.properties)?;
+
+        writer
+            .write(WriterEvent::end_element())
+            .map_err(TiledError::XmlEncodingError)
+    }
+
+    /// Serializes this map to a TMX file at `path`.
+    pub fn save_file(&self, path: &Path) -> Result<(), TiledError> {
+        let file = File::create(path).map_err(|err| TiledError::CouldNotOpenFile {
+            path: path.to_owned(),
+            err,
+        })?;
+        self.write_xml(file)
+    }
+}
+
+/// Writes a tileset out as a `<tileset>` element.
+///
+/// Per-tile data (custom properties, animations and images) and Wang sets
+/// are not yet written back out; a parse → write → parse round trip drops
+/// them from tiles that carry any.
+fn write_tileset<W: Write>(
+    writer: &mut EventWriter<W>,
+    tileset: &Tileset,
+) -> Result<(), TiledError> {
+    let first_gid_str = tileset.first_gid.0.to_string();
+    let tw_str = tileset.tile_width.to_string();
+    let th_str = tileset.tile_height.to_string();
+    let tc_str = tileset.tile_count.to_string();
+    let col_str = tileset.columns.to_string();
+    let mut start = WriterEvent::start_element("tileset")
+        .attr("firstgid", &first_gid_str)
+        .attr("name", &tileset.name)
+        .attr("tilewidth", &tw_str)
+        .attr("tileheight", &th_str)
+        .attr("tilecount", &tc_str)
+        .attr("columns", &col_str);
+    let spacing_str;
+    if tileset.spacing != 0 {
+        spacing_str = tileset.spacing.to_string();
+        start = start.attr("spacing", &spacing_str);
+    }
+    let margin_str;
+    if tileset.margin != 0 {
+        margin_str = tileset.margin.to_string();
+        start = start.attr("margin", &margin_str);
+    }
+    writer
+        .write(start)
+        .map_err(TiledError::XmlEncodingError)?;
+
+    if let Some(image) = &tileset.image {
+        let w_str = image.width.to_string();
+        let h_str = image.height.to_string();
+        writer
+            .write(
+                WriterEvent::start_element("image")
+                    .attr("source", &image.source)
+                    .attr("width", &w_str)
+                    .attr("height", &h_str),
+            )
+            .map_err(TiledError::XmlEncodingError)?;
+        writer
+            .write(WriterEvent::end_element())
+            .map_err(TiledError::XmlEncodingError)?;
+    }
+
+    write_properties(writer, &tileset.properties)?;
+
+    writer
+        .write(WriterEvent::end_element())
+        .map_err(TiledError::XmlEncodingError)
+}
+
+fn write_layer<W: Write>(writer: &mut EventWriter<W>, layer: &Layer) -> Result<(), TiledError> {
+    let mut start = WriterEvent::start_element("layer").attr("name", &layer.name);
+    let opacity_str;
+    if layer.opacity != 1.0 {
+        opacity_str = layer.opacity.to_string();
+        start = start.attr("opacity", &opacity_str);
+    }
+    if !layer.visible {
+        start = start.attr("visible", "0");
+    }
+    writer
+        .write(start)
+        .map_err(TiledError::XmlEncodingError)?;
+
+    writer
+        .write(WriterEvent::start_element("data").attr("encoding", "csv"))
+        .map_err(TiledError::XmlEncodingError)?;
+    match &layer.data {
+        LayerData::Finite(rows) => {
+            let csv = rows_to_csv(rows);
+            writer
+                .write(WriterEvent::characters(&csv))
+                .map_err(TiledError::XmlEncodingError)?;
+        }
+        LayerData::Infinite(chunks) => {
+            for chunk in chunks {
+                let x_str = chunk.x.to_string();
+                let y_str = chunk.y.to_string();
+                let w_str = chunk.width.to_string();
+                let h_str = chunk.height.to_string();
+                writer
+                    .write(
+                        WriterEvent::start_element("chunk")
+                            .attr("x", &x_str)
+                            .attr("y", &y_str)
+                            .attr("width", &w_str)
+                            .attr("height", &h_str),
+                    )
+                    .map_err(TiledError::XmlEncodingError)?;
+                let csv = chunk
+                    .tiles
+                    .chunks(chunk.width.max(1) as usize)
+                    .map(|row| row.to_vec())
+                    .collect::<Vec<_>>();
+                let csv = rows_to_csv(&csv);
+                writer
+                    .write(WriterEvent::characters(&csv))
+                    .map_err(TiledError::XmlEncodingError)?;
+                writer
+                    .write(WriterEvent::end_element())
+                    .map_err(TiledError::XmlEncodingError)?;
+            }
+        }
+    }
+    writer
+        .write(WriterEvent::end_element())
+        .map_err(TiledError::XmlEncodingError)?;
+
+    write_properties(writer, &layer.properties)?;
+
+    writer
+        .write(WriterEvent::end_element())
+        .map_err(TiledError::XmlEncodingError)
+}
+
+fn rows_to_csv(rows: &[Vec<crate::tile::LayerTile>]) -> String {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|tile| tile.to_raw().to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>()
+        .join(",\n")
+}
+
+fn write_image_layer<W: Write>(
+    writer: &mut EventWriter<W>,
+    image_layer: &ImageLayer,
+) -> Result<(), TiledError> {
+    let mut start = WriterEvent::start_element("imagelayer").attr("name", &image_layer.name);
+    let opacity_str;
+    if image_layer.opacity != 1.0 {
+        opacity_str = image_layer.opacity.to_string();
+        start = start.attr("opacity", &opacity_str);
+    }
+    if !image_layer.visible {
+        start = start.attr("visible", "0");
+    }
+    writer
+        .write(start)
+        .map_err(TiledError::XmlEncodingError)?;
+
+    if let Some(source) = &image_layer.image_source {
+        writer
+            .write(WriterEvent::start_element("image").attr("source", source.as_str()))
+            .map_err(TiledError::XmlEncodingError)?;
+        writer
+            .write(WriterEvent::end_element())
+            .map_err(TiledError::XmlEncodingError)?;
+    }
+
+    write_properties(writer, &image_layer.properties)?;
+
+    writer
+        .write(WriterEvent::end_element())
+        .map_err(TiledError::XmlEncodingError)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        layers::{Layer, LayerData},
+        map::{Map, Orientation},
+        properties::PropertyValue,
+        tile::LayerTile,
+    };
+
+    #[test]
+    fn write_then_parse_round_trips() {
+        let mut map_properties = HashMap::new();
+        map_properties.insert("author".to_string(), PropertyValue::StringValue("jan".to_string()));
+
+        let mut layer_properties = HashMap::new();
+        layer_properties.insert("solid".to_string(), PropertyValue::BoolValue(true));
+
+        let map = Map {
+            version: "1.10".to_string(),
+            orientation: Orientation::Orthogonal,
+            width: 2,
+            height: 2,
+            tile_width: 16,
+            tile_height: 16,
+            tilesets: Vec::new(),
+            layers: vec![Layer {
+                name: "Tile Layer 1".to_string(),
+                opacity: 1.0,
+                visible: true,
+                data: LayerData::Finite(vec![
+                    vec![LayerTile::from_raw(1), LayerTile::from_raw(2)],
+                    vec![LayerTile::from_raw(3), LayerTile::from_raw(4)],
+                ]),
+                layer_index: 0,
+                properties: layer_properties,
+            }],
+            image_layers: Vec::new(),
+            object_groups: Vec::new(),
+            properties: map_properties,
+            background_color: None,
+            infinite: false,
+            source: None,
+        };
+
+        let mut bytes = Vec::new();
+        map.write_xml(&mut bytes).unwrap();
+
+        let parsed = Map::parse_reader(bytes.as_slice(), None).unwrap();
+        assert_eq!(parsed, map);
+    }
+}